@@ -0,0 +1,160 @@
+//! Stopping long running operations at safe points.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Condition checked by long running operations at points where they can stop safely.
+pub trait Cancellation {
+    /// Check whether the operation should stop.
+    fn is_cancelled(&self) -> bool;
+}
+
+/// Never cancels.
+impl Cancellation for () {
+    fn is_cancelled(&self) -> bool {
+        false
+    }
+}
+
+impl<C: Cancellation + ?Sized> Cancellation for &C {
+    fn is_cancelled(&self) -> bool {
+        (**self).is_cancelled()
+    }
+}
+
+impl<C: Cancellation + ?Sized> Cancellation for Box<C> {
+    fn is_cancelled(&self) -> bool {
+        (**self).is_cancelled()
+    }
+}
+
+/// Cancels once at least one of the conditions does.
+impl<A: Cancellation, B: Cancellation> Cancellation for (A, B) {
+    fn is_cancelled(&self) -> bool {
+        self.0.is_cancelled() || self.1.is_cancelled()
+    }
+}
+
+/// Point in time after which operations should stop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Deadline {
+    instant: Instant,
+}
+
+impl Deadline {
+    /// Create a deadline expiring at `instant`.
+    pub fn at(instant: Instant) -> Self {
+        Self { instant }
+    }
+
+    /// Create a deadline expiring after `duration` has passed.
+    ///
+    /// Durations too large to be represented never expire in practice and are
+    /// clamped to the latest representable point in time.
+    pub fn after(duration: Duration) -> Self {
+        let now = Instant::now();
+        let mut duration = duration;
+        loop {
+            if let Some(instant) = now.checked_add(duration) {
+                return Self::at(instant);
+            }
+            duration /= 2;
+        }
+    }
+
+    /// Return the point in time at which the deadline expires.
+    pub fn instant(&self) -> Instant {
+        self.instant
+    }
+
+    /// Return the time remaining until the deadline expires.
+    pub fn remaining(&self) -> Duration {
+        self.instant.saturating_duration_since(Instant::now())
+    }
+
+    /// Check whether the deadline has expired.
+    pub fn is_expired(&self) -> bool {
+        Instant::now() >= self.instant
+    }
+}
+
+impl Cancellation for Deadline {
+    fn is_cancelled(&self) -> bool {
+        self.is_expired()
+    }
+}
+
+/// Flag which can be set from another thread to request cancellation.
+///
+/// Clones share the same flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Create a token which is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request cancellation of all operations observing this token or its clones.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Cancellation for CancellationToken {
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_never() {
+        assert!(!().is_cancelled());
+    }
+
+    #[test]
+    fn test_deadline() {
+        let deadline = Deadline::at(Instant::now());
+        assert!(deadline.is_expired());
+        assert!(deadline.is_cancelled());
+        assert_eq!(deadline.remaining(), Duration::ZERO);
+        let deadline = Deadline::after(Duration::from_secs(3600));
+        assert!(!deadline.is_expired());
+        assert!(deadline.remaining() > Duration::from_secs(3500));
+    }
+
+    #[test]
+    fn test_deadline_overflow() {
+        let deadline = Deadline::after(Duration::MAX);
+        assert!(!deadline.is_expired());
+    }
+
+    #[test]
+    fn test_token() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        assert!(!clone.is_cancelled());
+        thread::spawn(move || token.cancel()).join().unwrap();
+        assert!(clone.is_cancelled());
+    }
+
+    #[test]
+    fn test_combined() {
+        let token = CancellationToken::new();
+        let combined = (Deadline::after(Duration::from_secs(3600)), &token);
+        assert!(!combined.is_cancelled());
+        token.cancel();
+        assert!(combined.is_cancelled());
+        let boxed: Box<dyn Cancellation> = Box::new(Deadline::at(Instant::now()));
+        assert!((&boxed, ()).is_cancelled());
+    }
+}
@@ -0,0 +1,321 @@
+//! Validation and canonicalization of client identifiers.
+
+use crate::error::ErrorCode;
+use std::error::Error;
+use std::fmt;
+
+/// Policy turning user supplied client identifiers into their canonical form.
+///
+/// Registries and backends should pass every client identifier through the same policy
+/// before storing or comparing it, so that two spellings of the same client are never
+/// tracked as different clients.
+pub trait ClientIdPolicy {
+    /// Identifier type handled by this policy.
+    type ClientID;
+
+    /// Error returned for identifiers which are not acceptable.
+    type Error;
+
+    /// Validate `client` and return its canonical form.
+    fn canonicalize(&self, client: Self::ClientID) -> Result<Self::ClientID, Self::Error>;
+
+    /// Check whether two identifiers refer to the same client.
+    fn same_client(&self, a: Self::ClientID, b: Self::ClientID) -> Result<bool, Self::Error>
+    where
+        Self::ClientID: PartialEq,
+    {
+        Ok(self.canonicalize(a)? == self.canonicalize(b)?)
+    }
+}
+
+/// Reasons for rejecting a client name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvalidClientName {
+    /// The name is empty.
+    Empty,
+    /// The name is longer than the allowed number of characters.
+    TooLong { length: usize, limit: usize },
+    /// The name contains a character which is not allowed.
+    InvalidCharacter(char),
+    /// The name is reserved and can not be used by clients.
+    Reserved(String),
+}
+
+impl fmt::Display for InvalidClientName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "client name is empty"),
+            Self::TooLong { length, limit } => write!(
+                f,
+                "client name has {} characters, at most {} are allowed",
+                length, limit
+            ),
+            Self::InvalidCharacter(c) => {
+                write!(f, "client name contains invalid character {:?}", c)
+            }
+            Self::Reserved(name) => write!(f, "client name {:?} is reserved", name),
+        }
+    }
+}
+
+impl Error for InvalidClientName {}
+
+impl ErrorCode for InvalidClientName {
+    fn code(&self) -> &'static str {
+        match self {
+            Self::Empty => "client.empty",
+            Self::TooLong { .. } => "client.too_long",
+            Self::InvalidCharacter(_) => "client.invalid_character",
+            Self::Reserved(_) => "client.reserved",
+        }
+    }
+}
+
+/// [`ClientIdPolicy`] for textual client names.
+///
+/// Names are trimmed and checked against a length limit and a set of allowed characters,
+/// then optionally folded to lowercase and checked against a list of reserved names.
+/// Canonical names are accepted unchanged when canonicalized again.
+///
+/// Folding uses [`str::to_lowercase`], which is not full Unicode case folding:
+/// for example `"STRASSE"` and `"straße"` remain different names.
+/// Characters without a single character lowercase form, like `'İ'`, are rejected
+/// by case insensitive policies since folding them would change the length of the name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientNamePolicy {
+    case_insensitive: bool,
+    max_length: usize,
+    reserved: Vec<String>,
+}
+
+impl ClientNamePolicy {
+    /// Create a new policy with the given length limit.
+    ///
+    /// The policy is case insensitive and reserves no names.
+    pub fn new(max_length: usize) -> Self {
+        Self {
+            case_insensitive: true,
+            max_length,
+            reserved: Vec::new(),
+        }
+    }
+
+    /// Set whether names differing only in case refer to the same client.
+    pub fn case_insensitive(mut self, case_insensitive: bool) -> Self {
+        self.case_insensitive = case_insensitive;
+        self
+    }
+
+    /// Reserve a name, rejecting it for clients.
+    ///
+    /// The name is compared after folding both sides according to the
+    /// case sensitivity active when canonicalizing.
+    pub fn reserve(mut self, name: impl Into<String>) -> Self {
+        let name = name.into();
+        self.reserved.push(name.trim().to_owned());
+        self
+    }
+
+    fn fold(&self, name: &str) -> String {
+        if self.case_insensitive {
+            name.to_lowercase()
+        } else {
+            name.to_owned()
+        }
+    }
+
+    fn is_allowed(c: char) -> bool {
+        c.is_alphanumeric() || matches!(c, '-' | '_' | '.')
+    }
+
+    fn has_simple_lowercase(c: char) -> bool {
+        let mut lowercase = c.to_lowercase();
+        matches!((lowercase.next(), lowercase.next()), (Some(l), None) if Self::is_allowed(l))
+    }
+
+    fn validate(&self, name: &str) -> Result<(), InvalidClientName> {
+        let length = name.chars().count();
+        if length == 0 {
+            return Err(InvalidClientName::Empty);
+        }
+        if length > self.max_length {
+            return Err(InvalidClientName::TooLong {
+                length,
+                limit: self.max_length,
+            });
+        }
+        match name.chars().find(|c| {
+            !Self::is_allowed(*c) || (self.case_insensitive && !Self::has_simple_lowercase(*c))
+        }) {
+            Some(c) => Err(InvalidClientName::InvalidCharacter(c)),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Default for ClientNamePolicy {
+    fn default() -> Self {
+        Self::new(64)
+    }
+}
+
+impl ClientIdPolicy for ClientNamePolicy {
+    type ClientID = String;
+
+    type Error = InvalidClientName;
+
+    fn canonicalize(&self, client: String) -> Result<String, InvalidClientName> {
+        // validate the input first so errors refer to characters the user typed
+        let name = client.trim();
+        self.validate(name)?;
+        let name = self.fold(name);
+        self.validate(&name)?;
+        if self
+            .reserved
+            .iter()
+            .any(|reserved| self.fold(reserved) == name)
+        {
+            return Err(InvalidClientName::Reserved(name));
+        }
+        Ok(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn canonicalize(policy: &ClientNamePolicy, name: &str) -> Result<String, InvalidClientName> {
+        policy.canonicalize(name.to_owned())
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        let policy = ClientNamePolicy::default();
+        assert_eq!(
+            canonicalize(&policy, "  Laptop-1 "),
+            Ok("laptop-1".to_owned())
+        );
+        assert_eq!(canonicalize(&policy, "a.b_c"), Ok("a.b_c".to_owned()));
+        let policy = policy.case_insensitive(false);
+        assert_eq!(canonicalize(&policy, "Laptop"), Ok("Laptop".to_owned()));
+    }
+
+    #[test]
+    fn test_same_client() {
+        let policy = ClientNamePolicy::default();
+        assert_eq!(
+            policy.same_client("Laptop".to_owned(), " laptop".to_owned()),
+            Ok(true)
+        );
+        let policy = policy.case_insensitive(false);
+        assert_eq!(
+            policy.same_client("Laptop".to_owned(), "laptop".to_owned()),
+            Ok(false)
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        let policy = ClientNamePolicy::new(4);
+        assert_eq!(canonicalize(&policy, "  "), Err(InvalidClientName::Empty));
+        assert_eq!(
+            canonicalize(&policy, "abcde"),
+            Err(InvalidClientName::TooLong {
+                length: 5,
+                limit: 4
+            })
+        );
+        assert_eq!(
+            canonicalize(&policy, "a/b"),
+            Err(InvalidClientName::InvalidCharacter('/'))
+        );
+    }
+
+    #[test]
+    fn test_multi_char_lowercase() {
+        let policy = ClientNamePolicy::new(8);
+        assert_eq!(
+            canonicalize(&policy, "İstanbul"),
+            Err(InvalidClientName::InvalidCharacter('İ'))
+        );
+        let policy = policy.case_insensitive(false);
+        assert_eq!(canonicalize(&policy, "İstanbul"), Ok("İstanbul".to_owned()));
+    }
+
+    #[test]
+    fn test_idempotent() {
+        for policy in [
+            ClientNamePolicy::new(8),
+            ClientNamePolicy::new(8).case_insensitive(false),
+        ] {
+            for name in ["Laptop-1", "İstanbul", "ß", "ẞ", "Σ", "ΣΑΣ", "Straße"] {
+                if let Ok(canonical) = canonicalize(&policy, name) {
+                    assert_eq!(
+                        canonicalize(&policy, &canonical),
+                        Ok(canonical.clone()),
+                        "{:?} is not a fixed point",
+                        name
+                    );
+                    assert_eq!(policy.same_client(name.to_owned(), canonical), Ok(true));
+                }
+            }
+        }
+        let policy = ClientNamePolicy::default();
+        assert_eq!(canonicalize(&policy, "ẞ"), Ok("ß".to_owned()));
+        assert_eq!(canonicalize(&policy, "Σ"), Ok("σ".to_owned()));
+        assert_eq!(canonicalize(&policy, "ΣΑΣ"), Ok("σας".to_owned()));
+    }
+
+    #[test]
+    fn test_reserved() {
+        let policy = ClientNamePolicy::default().reserve(" Admin ");
+        assert_eq!(
+            canonicalize(&policy, "ADMIN"),
+            Err(InvalidClientName::Reserved("admin".to_owned()))
+        );
+        assert_eq!(canonicalize(&policy, "admins"), Ok("admins".to_owned()));
+    }
+
+    #[test]
+    fn test_reserved_order_independent() {
+        let policy = ClientNamePolicy::new(64)
+            .case_insensitive(false)
+            .reserve("Admin")
+            .case_insensitive(true);
+        assert_eq!(
+            canonicalize(&policy, "Admin"),
+            Err(InvalidClientName::Reserved("admin".to_owned()))
+        );
+        let policy = ClientNamePolicy::new(64)
+            .reserve("Admin")
+            .case_insensitive(false);
+        assert_eq!(
+            canonicalize(&policy, "Admin"),
+            Err(InvalidClientName::Reserved("Admin".to_owned()))
+        );
+        assert_eq!(canonicalize(&policy, "admin"), Ok("admin".to_owned()));
+    }
+
+    #[test]
+    fn test_error_codes() {
+        let errors = [
+            InvalidClientName::Empty,
+            InvalidClientName::TooLong {
+                length: 2,
+                limit: 1,
+            },
+            InvalidClientName::InvalidCharacter('/'),
+            InvalidClientName::Reserved("admin".to_owned()),
+        ];
+        assert_eq!(
+            errors.map(|error| error.code()),
+            [
+                "client.empty",
+                "client.too_long",
+                "client.invalid_character",
+                "client.reserved"
+            ]
+        );
+    }
+}
@@ -0,0 +1,508 @@
+//! Classification and handling of errors reported by backends.
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::slice;
+use std::thread;
+use std::time::Duration;
+use std::vec;
+
+/// Broad category of an error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCategory {
+    /// The operation failed temporarily and may succeed when repeated.
+    Transient,
+    /// The operation failed and repeating it will not help.
+    Permanent,
+    /// The object targeted by the operation does not exist.
+    NotFound,
+}
+
+impl ErrorCategory {
+    /// Classify an [`io::ErrorKind`].
+    ///
+    /// Interruptions, timeouts, busy resources and broken connections or
+    /// network routes are considered transient.
+    pub fn from_io_kind(kind: io::ErrorKind) -> Self {
+        match kind {
+            io::ErrorKind::NotFound => Self::NotFound,
+            io::ErrorKind::Interrupted
+            | io::ErrorKind::WouldBlock
+            | io::ErrorKind::TimedOut
+            | io::ErrorKind::ConnectionRefused
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::NotConnected
+            | io::ErrorKind::BrokenPipe
+            | io::ErrorKind::NetworkDown
+            | io::ErrorKind::NetworkUnreachable
+            | io::ErrorKind::HostUnreachable
+            | io::ErrorKind::ResourceBusy
+            | io::ErrorKind::StaleNetworkFileHandle => Self::Transient,
+            _ => Self::Permanent,
+        }
+    }
+
+    /// Classify the status code of a failed HTTP request.
+    ///
+    /// Timeouts, rate limiting and server side errors which are commonly
+    /// temporary are considered transient.
+    pub fn from_http_status(status: u16) -> Self {
+        match status {
+            404 | 410 => Self::NotFound,
+            408 | 425 | 429 | 500 | 502 | 503 | 504 => Self::Transient,
+            _ => Self::Permanent,
+        }
+    }
+}
+
+/// Errors which can be classified into an [`ErrorCategory`].
+pub trait ErrorClass {
+    /// Return the category of this error.
+    fn category(&self) -> ErrorCategory;
+
+    /// Check whether repeating the failed operation may succeed.
+    fn is_transient(&self) -> bool {
+        self.category() == ErrorCategory::Transient
+    }
+
+    /// Check whether the operation failed because its target does not exist.
+    fn is_not_found(&self) -> bool {
+        self.category() == ErrorCategory::NotFound
+    }
+}
+
+impl ErrorClass for io::Error {
+    fn category(&self) -> ErrorCategory {
+        ErrorCategory::from_io_kind(self.kind())
+    }
+}
+
+impl ErrorClass for io::ErrorKind {
+    fn category(&self) -> ErrorCategory {
+        ErrorCategory::from_io_kind(*self)
+    }
+}
+
+/// Errors carrying a stable code which identifies the kind of failure.
+///
+/// Codes are lowercase strings of the form `<module>.<failure>`, for example
+/// `client.empty`. A released code is never changed or reused for a different failure,
+/// allowing applications and foreign language bindings to branch on failures
+/// without matching error messages. Every error type of this crate with its own
+/// failure variants implements this trait.
+pub trait ErrorCode {
+    /// Return the code of this error.
+    fn code(&self) -> &'static str;
+}
+
+/// Treat a failure because of a missing object as success.
+///
+/// Operations which are idempotent, like creating, recovering or deleting fossils,
+/// may find their target already processed by an earlier interrupted attempt.
+pub fn ignore_not_found<E: ErrorClass>(result: Result<(), E>) -> Result<(), E> {
+    match result {
+        Err(error) if error.is_not_found() => Ok(()),
+        result => result,
+    }
+}
+
+/// Policy for repeating operations which failed with a transient error.
+///
+/// Permanent and not-found errors are returned immediately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Retry {
+    attempts: u32,
+    delay: Duration,
+    max_delay: Duration,
+}
+
+impl Retry {
+    /// Create a policy executing an operation at most `attempts` times.
+    ///
+    /// Attempts are not delayed by default.
+    pub fn new(attempts: u32) -> Self {
+        Self {
+            attempts: attempts.max(1),
+            delay: Duration::ZERO,
+            max_delay: Duration::MAX,
+        }
+    }
+
+    /// Set the delay before the first retry, which doubles on every further retry.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Set the upper limit for the delay between retries.
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Return the maximum number of times an operation is executed.
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    /// Return the delay before retrying after the failed attempt `attempt`, starting at 1.
+    fn delay_before(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.delay.saturating_mul(factor).min(self.max_delay)
+    }
+
+    /// Execute `operation` until it succeeds, fails with a non transient error
+    /// or the number of attempts is exhausted.
+    pub fn call<T, E, F>(&self, mut operation: F) -> Result<T, E>
+    where
+        E: ErrorClass,
+        F: FnMut() -> Result<T, E>,
+    {
+        let mut attempt = 1;
+        loop {
+            match operation() {
+                Err(error) if error.is_transient() && attempt < self.attempts => {
+                    let delay = self.delay_before(attempt);
+                    if !delay.is_zero() {
+                        thread::sleep(delay);
+                    }
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl Default for Retry {
+    fn default() -> Self {
+        Self::new(3).delay(Duration::from_millis(100))
+    }
+}
+
+/// Collection of errors returned by operations which continue after failures.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Errors<E> {
+    errors: Vec<E>,
+}
+
+impl<E> Errors<E> {
+    /// Create an empty collection.
+    pub fn new() -> Self {
+        Self { errors: Vec::new() }
+    }
+
+    /// Add an error to the collection.
+    pub fn push(&mut self, error: E) {
+        self.errors.push(error);
+    }
+
+    /// Return the number of errors.
+    pub fn len(&self) -> usize {
+        self.errors.len()
+    }
+
+    /// Check whether the collection contains no errors.
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Return an iterator over the errors.
+    pub fn iter(&self) -> slice::Iter<'_, E> {
+        self.errors.iter()
+    }
+
+    /// Return `Ok(value)` if no errors were collected, else `Err(self)`.
+    pub fn into_result<T>(self, value: T) -> Result<T, Self> {
+        if self.is_empty() {
+            Ok(value)
+        } else {
+            Err(self)
+        }
+    }
+
+    /// Convert into the contained errors.
+    pub fn into_inner(self) -> Vec<E> {
+        self.errors
+    }
+}
+
+impl<E> Default for Errors<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E> From<Vec<E>> for Errors<E> {
+    fn from(errors: Vec<E>) -> Self {
+        Self { errors }
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for Errors<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.errors.as_slice() {
+            [] => write!(f, "no errors occurred"),
+            [error] => error.fmt(f),
+            errors => {
+                write!(f, "{} errors occurred:", errors.len())?;
+                for error in errors {
+                    write!(f, "\n- {}", error)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The collected errors are already part of the message and are not reported as source,
+/// use [`Errors::iter`] to inspect them.
+impl<E: Error> Error for Errors<E> {}
+
+impl<E> Extend<E> for Errors<E> {
+    fn extend<I: IntoIterator<Item = E>>(&mut self, iter: I) {
+        self.errors.extend(iter);
+    }
+}
+
+impl<E> FromIterator<E> for Errors<E> {
+    fn from_iter<I: IntoIterator<Item = E>>(iter: I) -> Self {
+        Self {
+            errors: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<E> IntoIterator for Errors<E> {
+    type Item = E;
+
+    type IntoIter = vec::IntoIter<E>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.errors.into_iter()
+    }
+}
+
+impl<'a, E> IntoIterator for &'a Errors<E> {
+    type Item = &'a E;
+
+    type IntoIter = slice::Iter<'a, E>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.errors.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    struct TestError(ErrorCategory);
+
+    impl ErrorClass for TestError {
+        fn category(&self) -> ErrorCategory {
+            self.0
+        }
+    }
+
+    fn failing(
+        calls: &Cell<u32>,
+        category: ErrorCategory,
+        succeed_at: u32,
+    ) -> impl FnMut() -> Result<u32, TestError> + '_ {
+        move || {
+            calls.set(calls.get() + 1);
+            if calls.get() >= succeed_at {
+                Ok(calls.get())
+            } else {
+                Err(TestError(category))
+            }
+        }
+    }
+
+    #[derive(Debug)]
+    struct Boom;
+
+    impl fmt::Display for Boom {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "boom")
+        }
+    }
+
+    impl Error for Boom {}
+
+    #[test]
+    fn test_from_io_kind() {
+        use io::ErrorKind::*;
+        assert_eq!(
+            ErrorCategory::from_io_kind(NotFound),
+            ErrorCategory::NotFound
+        );
+        for kind in [
+            Interrupted,
+            WouldBlock,
+            TimedOut,
+            ConnectionRefused,
+            ConnectionReset,
+            ConnectionAborted,
+            NotConnected,
+            BrokenPipe,
+            NetworkDown,
+            NetworkUnreachable,
+            HostUnreachable,
+            ResourceBusy,
+            StaleNetworkFileHandle,
+        ] {
+            assert_eq!(ErrorCategory::from_io_kind(kind), ErrorCategory::Transient);
+        }
+        for kind in [
+            PermissionDenied,
+            AlreadyExists,
+            InvalidData,
+            UnexpectedEof,
+            Other,
+        ] {
+            assert_eq!(ErrorCategory::from_io_kind(kind), ErrorCategory::Permanent);
+        }
+    }
+
+    #[test]
+    fn test_io_error_class() {
+        let error = io::Error::new(io::ErrorKind::TimedOut, "timeout");
+        assert!(error.is_transient());
+        assert!(!error.is_not_found());
+        assert!(io::ErrorKind::NotFound.is_not_found());
+    }
+
+    #[test]
+    fn test_from_http_status() {
+        for status in [404, 410] {
+            assert_eq!(
+                ErrorCategory::from_http_status(status),
+                ErrorCategory::NotFound
+            );
+        }
+        for status in [408, 425, 429, 500, 502, 503, 504] {
+            assert_eq!(
+                ErrorCategory::from_http_status(status),
+                ErrorCategory::Transient
+            );
+        }
+        for status in [400, 401, 403, 409, 412, 501, 505] {
+            assert_eq!(
+                ErrorCategory::from_http_status(status),
+                ErrorCategory::Permanent
+            );
+        }
+    }
+
+    #[test]
+    fn test_ignore_not_found() {
+        assert_eq!(ignore_not_found::<TestError>(Ok(())), Ok(()));
+        assert_eq!(
+            ignore_not_found(Err(TestError(ErrorCategory::NotFound))),
+            Ok(())
+        );
+        for category in [ErrorCategory::Transient, ErrorCategory::Permanent] {
+            assert_eq!(
+                ignore_not_found(Err(TestError(category))),
+                Err(TestError(category))
+            );
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut errors = Errors::new();
+        assert!(errors.is_empty());
+        errors.push("one");
+        errors.extend(["two", "three"]);
+        assert_eq!(errors.len(), 3);
+        assert_eq!(
+            errors.iter().copied().collect::<Vec<_>>(),
+            ["one", "two", "three"]
+        );
+        assert_eq!((&errors).into_iter().count(), 3);
+        assert_eq!(
+            errors.clone().into_iter().collect::<Vec<_>>(),
+            errors.into_inner()
+        );
+    }
+
+    #[test]
+    fn test_errors_into_result() {
+        assert_eq!(Errors::<&str>::new().into_result(1), Ok(1));
+        let errors = Errors::from(vec!["one"]);
+        assert_eq!(errors.clone().into_result(1), Err(errors));
+    }
+
+    #[test]
+    fn test_errors_display() {
+        assert_eq!(Errors::<&str>::new().to_string(), "no errors occurred");
+        assert_eq!(Errors::from(vec!["one"]).to_string(), "one");
+        assert_eq!(
+            Errors::from(vec!["one", "two"]).to_string(),
+            "2 errors occurred:\n- one\n- two"
+        );
+    }
+
+    #[test]
+    fn test_errors_source() {
+        let errors: Errors<Boom> = [Boom, Boom].into_iter().collect();
+        assert!(errors.source().is_none());
+        let errors = Errors::from(vec![Boom]);
+        assert!(errors.source().is_none());
+        assert_eq!(errors.to_string(), "boom");
+    }
+
+    #[test]
+    fn test_retry_transient() {
+        let calls = Cell::new(0);
+        let result = Retry::new(3).call(failing(&calls, ErrorCategory::Transient, 3));
+        assert_eq!(result, Ok(3));
+        assert_eq!(calls.get(), 3);
+    }
+
+    #[test]
+    fn test_retry_exhausted() {
+        let calls = Cell::new(0);
+        let result = Retry::new(3).call(failing(&calls, ErrorCategory::Transient, 10));
+        assert_eq!(result, Err(TestError(ErrorCategory::Transient)));
+        assert_eq!(calls.get(), 3);
+    }
+
+    #[test]
+    fn test_retry_not_transient() {
+        for category in [ErrorCategory::Permanent, ErrorCategory::NotFound] {
+            let calls = Cell::new(0);
+            let result = Retry::new(3).call(failing(&calls, category, 10));
+            assert_eq!(result, Err(TestError(category)));
+            assert_eq!(calls.get(), 1);
+        }
+    }
+
+    #[test]
+    fn test_retry_zero_attempts() {
+        let retry = Retry::new(0);
+        assert_eq!(retry.attempts(), 1);
+        let calls = Cell::new(0);
+        let result = retry.call(failing(&calls, ErrorCategory::Transient, 10));
+        assert_eq!(result, Err(TestError(ErrorCategory::Transient)));
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[test]
+    fn test_retry_backoff() {
+        let retry = Retry::new(10)
+            .delay(Duration::from_millis(100))
+            .max_delay(Duration::from_millis(500));
+        let delays: Vec<_> = (1..=5).map(|attempt| retry.delay_before(attempt)).collect();
+        assert_eq!(delays, [100, 200, 400, 500, 500].map(Duration::from_millis));
+        assert_eq!(retry.delay_before(u32::MAX), Duration::from_millis(500));
+        assert_eq!(Retry::new(3).delay_before(2), Duration::ZERO);
+    }
+}
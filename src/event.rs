@@ -0,0 +1,228 @@
+//! Typed events emitted while operating on a repository.
+
+use std::fmt::Display;
+use std::io::{self, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Event emitted by operations mutating or inspecting a repository.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Event<ChunkID, FossilID, ArchiveID, ClientID> {
+    /// A chunk was turned into a fossil.
+    FossilCreated { chunk: ChunkID, fossil: FossilID },
+    /// A fossil was turned back into a chunk.
+    FossilRecovered(FossilID),
+    /// A fossil was deleted permanently.
+    FossilDeleted(FossilID),
+    /// An archive was inspected.
+    ArchiveSeen(ArchiveID),
+    /// A client satisfied the validity requirement for deleting fossils.
+    ClientValidated(ClientID),
+}
+
+/// Receiver of events.
+///
+/// Sinks are generic over the event type, which is usually an [`Event`].
+pub trait EventSink<E> {
+    /// Called for every event in the order they occur.
+    fn emit(&mut self, event: E);
+}
+
+/// Ignores all events.
+impl<E> EventSink<E> for () {
+    fn emit(&mut self, _event: E) {}
+}
+
+/// Records all events, which is useful for assertions in tests.
+impl<E> EventSink<E> for Vec<E> {
+    fn emit(&mut self, event: E) {
+        self.push(event)
+    }
+}
+
+impl<E, S: EventSink<E> + ?Sized> EventSink<E> for &mut S {
+    fn emit(&mut self, event: E) {
+        (**self).emit(event)
+    }
+}
+
+impl<E, S: EventSink<E> + ?Sized> EventSink<E> for Box<S> {
+    fn emit(&mut self, event: E) {
+        (**self).emit(event)
+    }
+}
+
+/// Append-only log of destructive events written to a [`Write`].
+///
+/// Every fossil creation and deletion is written as a line containing the time in
+/// seconds since the Unix epoch, the identity of the fossil collection, the action and
+/// the involved IDs, for example `1700000000.123 collection-1 create chunk=ab fossil=cd`.
+/// Other events are ignored. Every line is flushed before returning, so that calling
+/// [`AuditLog::record`] before performing an action leaves a trace even if the action
+/// is interrupted.
+#[derive(Debug)]
+pub struct AuditLog<W> {
+    writer: W,
+    collection: String,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> AuditLog<W> {
+    /// Create a log writing events of the fossil collection identified by `collection`.
+    pub fn new(writer: W, collection: impl Into<String>) -> Self {
+        Self {
+            writer,
+            collection: collection.into(),
+            error: None,
+        }
+    }
+
+    /// Write `event` to the log if it is destructive.
+    pub fn record<C, F, A, Cl>(&mut self, event: &Event<C, F, A, Cl>) -> io::Result<()>
+    where
+        C: Display,
+        F: Display,
+    {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let prefix = format!(
+            "{}.{:03} {}",
+            timestamp.as_secs(),
+            timestamp.subsec_millis(),
+            self.collection
+        );
+        match event {
+            Event::FossilCreated { chunk, fossil } => writeln!(
+                self.writer,
+                "{} create chunk={} fossil={}",
+                prefix, chunk, fossil
+            )?,
+            Event::FossilDeleted(fossil) => {
+                writeln!(self.writer, "{} delete fossil={}", prefix, fossil)?
+            }
+            _ => return Ok(()),
+        }
+        self.writer.flush()
+    }
+
+    /// Return the first error which occurred while emitting events, if any.
+    ///
+    /// Operations should stop performing destructive actions once this returns an error,
+    /// since they are no longer recorded. Emitting stops writing until the error is taken.
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+
+    /// Return the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write, C: Display, F: Display, A, Cl> EventSink<Event<C, F, A, Cl>> for AuditLog<W> {
+    fn emit(&mut self, event: Event<C, F, A, Cl>) {
+        if self.error.is_none() {
+            if let Err(error) = self.record(&event) {
+                self.error = Some(error);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type TestEvent = Event<u8, u16, u32, &'static str>;
+
+    fn emit_all(mut sink: impl EventSink<TestEvent>) {
+        sink.emit(Event::ArchiveSeen(1));
+        sink.emit(Event::FossilCreated {
+            chunk: 2,
+            fossil: 3,
+        });
+        sink.emit(Event::ClientValidated("laptop"));
+    }
+
+    #[test]
+    fn test_record() {
+        let mut events = Vec::new();
+        emit_all(&mut events);
+        let mut sink: Box<dyn EventSink<TestEvent>> = Box::new(&mut events);
+        sink.emit(Event::FossilRecovered(3));
+        sink.emit(Event::FossilDeleted(4));
+        drop(sink);
+        assert_eq!(
+            events,
+            [
+                Event::ArchiveSeen(1),
+                Event::FossilCreated {
+                    chunk: 2,
+                    fossil: 3
+                },
+                Event::ClientValidated("laptop"),
+                Event::FossilRecovered(3),
+                Event::FossilDeleted(4),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ignore() {
+        emit_all(());
+    }
+
+    struct FailingWriter;
+
+    impl Write for FailingWriter {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            Err(io::Error::other("disk full"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_audit_log() {
+        let mut log = AuditLog::new(Vec::new(), "collection-1");
+        emit_all(&mut log);
+        let events: [TestEvent; 2] = [Event::FossilRecovered(3), Event::FossilDeleted(4)];
+        for event in events {
+            log.emit(event);
+        }
+        assert!(log.take_error().is_none());
+        let output = String::from_utf8(log.into_inner()).unwrap();
+        let lines: Vec<_> = output
+            .lines()
+            .map(|line| {
+                let (timestamp, rest) = line.split_once(' ').unwrap();
+                assert!(timestamp.parse::<f64>().unwrap() > 0.0);
+                rest
+            })
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                "collection-1 create chunk=2 fossil=3",
+                "collection-1 delete fossil=4"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_audit_log_error() {
+        let mut log = AuditLog::new(FailingWriter, "collection-1");
+        let event: TestEvent = Event::FossilDeleted(4);
+        assert!(log.record(&event).is_err());
+        assert!(log
+            .record(&Event::<u8, u16, u32, ()>::ArchiveSeen(1))
+            .is_ok());
+        emit_all(&mut log);
+        assert!(log.take_error().is_some());
+        log.emit(event);
+        assert_eq!(log.take_error().unwrap().to_string(), "disk full");
+        assert!(log.take_error().is_none());
+    }
+}
@@ -0,0 +1,126 @@
+//! Recording repository mutations which might be left unfinished.
+
+use std::fmt;
+use std::mem;
+
+/// Guard tracking items, like fossils, which were mutated but not yet committed.
+///
+/// If the guard is dropped while uncommitted items remain, for example because an
+/// operation returned early or panicked, the items are passed to the sink, which should
+/// persist them somewhere to allow repairing the repository later.
+pub struct MutationGuard<T, S: FnOnce(Vec<T>)> {
+    pending: Vec<T>,
+    sink: Option<S>,
+}
+
+impl<T, S: FnOnce(Vec<T>)> MutationGuard<T, S> {
+    /// Create a guard passing uncommitted items to `sink` when dropped.
+    pub fn new(sink: S) -> Self {
+        Self {
+            pending: Vec::new(),
+            sink: Some(sink),
+        }
+    }
+
+    /// Record that `item` was mutated.
+    pub fn record(&mut self, item: T) {
+        self.pending.push(item);
+    }
+
+    /// Return the items mutated since the last commit.
+    pub fn pending(&self) -> &[T] {
+        &self.pending
+    }
+
+    /// Mark all pending items as committed and return them.
+    ///
+    /// This should be called once the mutations are recorded persistently,
+    /// for example in a stored fossil collection.
+    pub fn commit(&mut self) -> Vec<T> {
+        mem::take(&mut self.pending)
+    }
+
+    /// Commit all pending items and disarm the guard without calling the sink.
+    pub fn finish(mut self) -> Vec<T> {
+        self.sink = None;
+        self.commit()
+    }
+}
+
+impl<T: fmt::Debug, S: FnOnce(Vec<T>)> fmt::Debug for MutationGuard<T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MutationGuard")
+            .field("pending", &self.pending)
+            .field("armed", &self.sink.is_some())
+            .finish()
+    }
+}
+
+impl<T, S: FnOnce(Vec<T>)> Drop for MutationGuard<T, S> {
+    fn drop(&mut self) {
+        if let Some(sink) = self.sink.take() {
+            if !self.pending.is_empty() {
+                sink(mem::take(&mut self.pending));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic;
+    use std::sync::{Arc, Mutex};
+
+    type Reported = Arc<Mutex<Vec<Vec<u32>>>>;
+
+    fn sink() -> (Reported, impl FnOnce(Vec<u32>)) {
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let clone = Arc::clone(&reported);
+        (reported, move |items| clone.lock().unwrap().push(items))
+    }
+
+    #[test]
+    fn test_drop() {
+        let (reported, sink) = sink();
+        let mut guard = MutationGuard::new(sink);
+        guard.record(1);
+        guard.record(2);
+        assert_eq!(guard.commit(), [1, 2]);
+        guard.record(3);
+        assert_eq!(guard.pending(), [3]);
+        drop(guard);
+        assert_eq!(*reported.lock().unwrap(), [vec![3]]);
+    }
+
+    #[test]
+    fn test_unwind() {
+        let (reported, sink) = sink();
+        let result = panic::catch_unwind(move || {
+            let mut guard = MutationGuard::new(sink);
+            guard.record(1);
+            panic!("interrupted");
+        });
+        assert!(result.is_err());
+        assert_eq!(*reported.lock().unwrap(), [vec![1]]);
+    }
+
+    #[test]
+    fn test_nothing_pending() {
+        let (reported, sink) = sink();
+        let mut guard = MutationGuard::new(sink);
+        guard.record(1);
+        guard.commit();
+        drop(guard);
+        assert!(reported.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_finish() {
+        let (reported, sink) = sink();
+        let mut guard = MutationGuard::new(sink);
+        guard.record(1);
+        assert_eq!(guard.finish(), [1]);
+        assert!(reported.lock().unwrap().is_empty());
+    }
+}
@@ -1 +1,10 @@
+//! Implementation of Lock-Free Deduplication.
 
+pub mod cancel;
+pub mod client;
+pub mod error;
+pub mod event;
+pub mod guard;
+pub mod metrics;
+pub mod outcome;
+pub mod progress;
@@ -0,0 +1,259 @@
+//! Hooks for collecting metrics about operations.
+
+use std::sync::Arc;
+
+/// Receiver of metric updates.
+///
+/// Metrics are identified by static names and are shared between threads,
+/// which is why all methods take `&self`.
+pub trait Metrics {
+    /// Increase the counter `name` by `value`.
+    fn increment_counter(&self, name: &'static str, value: u64);
+
+    /// Set the gauge `name` to `value`.
+    fn set_gauge(&self, name: &'static str, value: f64);
+
+    /// Record `value` in the histogram `name`.
+    fn record_histogram(&self, name: &'static str, value: f64);
+}
+
+/// Ignores all updates.
+impl Metrics for () {
+    fn increment_counter(&self, _name: &'static str, _value: u64) {}
+
+    fn set_gauge(&self, _name: &'static str, _value: f64) {}
+
+    fn record_histogram(&self, _name: &'static str, _value: f64) {}
+}
+
+impl<M: Metrics + ?Sized> Metrics for &M {
+    fn increment_counter(&self, name: &'static str, value: u64) {
+        (**self).increment_counter(name, value)
+    }
+
+    fn set_gauge(&self, name: &'static str, value: f64) {
+        (**self).set_gauge(name, value)
+    }
+
+    fn record_histogram(&self, name: &'static str, value: f64) {
+        (**self).record_histogram(name, value)
+    }
+}
+
+impl<M: Metrics + ?Sized> Metrics for Box<M> {
+    fn increment_counter(&self, name: &'static str, value: u64) {
+        (**self).increment_counter(name, value)
+    }
+
+    fn set_gauge(&self, name: &'static str, value: f64) {
+        (**self).set_gauge(name, value)
+    }
+
+    fn record_histogram(&self, name: &'static str, value: f64) {
+        (**self).record_histogram(name, value)
+    }
+}
+
+impl<M: Metrics + ?Sized> Metrics for Arc<M> {
+    fn increment_counter(&self, name: &'static str, value: u64) {
+        (**self).increment_counter(name, value)
+    }
+
+    fn set_gauge(&self, name: &'static str, value: f64) {
+        (**self).set_gauge(name, value)
+    }
+
+    fn record_histogram(&self, name: &'static str, value: f64) {
+        (**self).record_histogram(name, value)
+    }
+}
+
+/// [`Metrics`] forwarding all updates to the recorder installed in the `metrics` crate.
+#[cfg(feature = "metrics")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct MetricsFacade;
+
+#[cfg(feature = "metrics")]
+impl Metrics for MetricsFacade {
+    fn increment_counter(&self, name: &'static str, value: u64) {
+        ::metrics::counter!(name).increment(value)
+    }
+
+    fn set_gauge(&self, name: &'static str, value: f64) {
+        ::metrics::gauge!(name).set(value)
+    }
+
+    fn record_histogram(&self, name: &'static str, value: f64) {
+        ::metrics::histogram!(name).record(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Debug, Default)]
+    struct Recorder {
+        updates: Mutex<Vec<(&'static str, &'static str, f64)>>,
+    }
+
+    impl Metrics for Recorder {
+        fn increment_counter(&self, name: &'static str, value: u64) {
+            self.updates
+                .lock()
+                .unwrap()
+                .push(("counter", name, value as f64));
+        }
+
+        fn set_gauge(&self, name: &'static str, value: f64) {
+            self.updates.lock().unwrap().push(("gauge", name, value));
+        }
+
+        fn record_histogram(&self, name: &'static str, value: f64) {
+            self.updates
+                .lock()
+                .unwrap()
+                .push(("histogram", name, value));
+        }
+    }
+
+    fn report(metrics: impl Metrics) {
+        metrics.increment_counter("fossils", 2);
+        metrics.set_gauge("archives", 3.0);
+        metrics.record_histogram("latency", 0.5);
+    }
+
+    #[test]
+    fn test_forwarding() {
+        let recorder = Recorder::default();
+        report(&recorder);
+        assert_eq!(
+            recorder.updates.into_inner().unwrap(),
+            [
+                ("counter", "fossils", 2.0),
+                ("gauge", "archives", 3.0),
+                ("histogram", "latency", 0.5)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_smart_pointers() {
+        let recorder = Arc::new(Recorder::default());
+        report(Box::new(Arc::clone(&recorder)) as Box<dyn Metrics>);
+        assert_eq!(recorder.updates.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_noop() {
+        report(());
+    }
+
+    #[cfg(feature = "metrics")]
+    mod facade {
+        use super::super::*;
+        use super::report;
+        use ::metrics::{
+            Counter, CounterFn, Gauge, GaugeFn, Histogram, HistogramFn, Key, KeyName, Metadata,
+            SharedString, Unit,
+        };
+        use std::sync::Mutex;
+
+        type Updates = Arc<Mutex<Vec<(&'static str, String, f64)>>>;
+
+        struct Handle {
+            kind: &'static str,
+            name: String,
+            updates: Updates,
+        }
+
+        impl Handle {
+            fn push(&self, value: f64) {
+                self.updates
+                    .lock()
+                    .unwrap()
+                    .push((self.kind, self.name.clone(), value));
+            }
+        }
+
+        impl CounterFn for Handle {
+            fn increment(&self, value: u64) {
+                self.push(value as f64)
+            }
+
+            fn absolute(&self, value: u64) {
+                self.push(value as f64)
+            }
+        }
+
+        impl GaugeFn for Handle {
+            fn increment(&self, value: f64) {
+                self.push(value)
+            }
+
+            fn decrement(&self, value: f64) {
+                self.push(-value)
+            }
+
+            fn set(&self, value: f64) {
+                self.push(value)
+            }
+        }
+
+        impl HistogramFn for Handle {
+            fn record(&self, value: f64) {
+                self.push(value)
+            }
+        }
+
+        #[derive(Default)]
+        struct TestRecorder {
+            updates: Updates,
+        }
+
+        impl TestRecorder {
+            fn handle(&self, kind: &'static str, key: &Key) -> Arc<Handle> {
+                Arc::new(Handle {
+                    kind,
+                    name: key.name().to_owned(),
+                    updates: Arc::clone(&self.updates),
+                })
+            }
+        }
+
+        impl ::metrics::Recorder for TestRecorder {
+            fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+            fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+            fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+            fn register_counter(&self, key: &Key, _: &Metadata<'_>) -> Counter {
+                Counter::from_arc(self.handle("counter", key))
+            }
+
+            fn register_gauge(&self, key: &Key, _: &Metadata<'_>) -> Gauge {
+                Gauge::from_arc(self.handle("gauge", key))
+            }
+
+            fn register_histogram(&self, key: &Key, _: &Metadata<'_>) -> Histogram {
+                Histogram::from_arc(self.handle("histogram", key))
+            }
+        }
+
+        #[test]
+        fn test_facade() {
+            let recorder = TestRecorder::default();
+            ::metrics::with_local_recorder(&recorder, || report(MetricsFacade));
+            assert_eq!(
+                *recorder.updates.lock().unwrap(),
+                [
+                    ("counter", "fossils".to_owned(), 2.0),
+                    ("gauge", "archives".to_owned(), 3.0),
+                    ("histogram", "latency".to_owned(), 0.5)
+                ]
+            );
+        }
+    }
+}
@@ -0,0 +1,188 @@
+//! Results of batch operations which may partially fail.
+
+use crate::error::Errors;
+
+/// Outcome of an operation applied to many items.
+///
+/// Instead of aborting on the first error, every item is recorded either as success
+/// or together with the error it failed with, allowing callers to retry only the failed items.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartialOutcome<T, E> {
+    succeeded: Vec<T>,
+    failed: Vec<(T, E)>,
+}
+
+impl<T, E> PartialOutcome<T, E> {
+    /// Create an empty outcome.
+    pub fn new() -> Self {
+        Self {
+            succeeded: Vec::new(),
+            failed: Vec::new(),
+        }
+    }
+
+    /// Record that the operation succeeded for `item`.
+    pub fn push_success(&mut self, item: T) {
+        self.succeeded.push(item);
+    }
+
+    /// Record that the operation failed for `item` with `error`.
+    pub fn push_failure(&mut self, item: T, error: E) {
+        self.failed.push((item, error));
+    }
+
+    /// Record the result of the operation for `item`.
+    pub fn record(&mut self, item: T, result: Result<(), E>) {
+        match result {
+            Ok(()) => self.push_success(item),
+            Err(error) => self.push_failure(item, error),
+        }
+    }
+
+    /// Return the items for which the operation succeeded.
+    pub fn succeeded(&self) -> &[T] {
+        &self.succeeded
+    }
+
+    /// Return the items for which the operation failed and their errors.
+    pub fn failed(&self) -> &[(T, E)] {
+        &self.failed
+    }
+
+    /// Return the number of recorded items.
+    pub fn len(&self) -> usize {
+        self.succeeded.len() + self.failed.len()
+    }
+
+    /// Check whether no items were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Check whether the operation succeeded for every item.
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+
+    /// Return an iterator over the items which have to be retried.
+    pub fn retry_items(&self) -> impl Iterator<Item = &T> {
+        self.failed.iter().map(|(item, _)| item)
+    }
+
+    /// Merge the items recorded by `other` into this outcome.
+    pub fn merge(&mut self, other: Self) {
+        self.succeeded.extend(other.succeeded);
+        self.failed.extend(other.failed);
+    }
+
+    /// Convert into a [`Result`] which is only [`Ok`] if no item failed.
+    pub fn into_result(self) -> Result<Vec<T>, Self> {
+        if self.is_complete() {
+            Ok(self.succeeded)
+        } else {
+            Err(self)
+        }
+    }
+
+    /// Destructure into the succeeded and failed items.
+    pub fn into_inner(self) -> (Vec<T>, Vec<(T, E)>) {
+        (self.succeeded, self.failed)
+    }
+
+    /// Return the errors, discarding the items.
+    pub fn into_errors(self) -> Errors<E> {
+        self.failed.into_iter().map(|(_, error)| error).collect()
+    }
+}
+
+impl<T, E> Default for PartialOutcome<T, E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, E> Extend<(T, Result<(), E>)> for PartialOutcome<T, E> {
+    fn extend<I: IntoIterator<Item = (T, Result<(), E>)>>(&mut self, iter: I) {
+        for (item, result) in iter {
+            self.record(item, result);
+        }
+    }
+}
+
+impl<T, E> FromIterator<(T, Result<(), E>)> for PartialOutcome<T, E> {
+    fn from_iter<I: IntoIterator<Item = (T, Result<(), E>)>>(iter: I) -> Self {
+        let mut outcome = Self::new();
+        outcome.extend(iter);
+        outcome
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outcome() -> PartialOutcome<u32, &'static str> {
+        [(1, Ok(())), (2, Err("two")), (3, Ok(())), (4, Err("four"))]
+            .into_iter()
+            .collect()
+    }
+
+    #[test]
+    fn test_record() {
+        let mut outcome = PartialOutcome::new();
+        assert!(outcome.is_empty());
+        assert!(outcome.is_complete());
+        outcome.record(1, Ok(()));
+        outcome.record(2, Err("two"));
+        assert_eq!(outcome.succeeded(), [1]);
+        assert_eq!(outcome.failed(), [(2, "two")]);
+        assert_eq!(outcome.len(), 2);
+        assert!(!outcome.is_empty());
+        assert!(!outcome.is_complete());
+    }
+
+    #[test]
+    fn test_from_iter() {
+        let outcome = outcome();
+        assert_eq!(outcome.succeeded(), [1, 3]);
+        assert_eq!(outcome.failed(), [(2, "two"), (4, "four")]);
+        assert_eq!(outcome.retry_items().copied().collect::<Vec<_>>(), [2, 4]);
+    }
+
+    #[test]
+    fn test_extend() {
+        let mut outcome = outcome();
+        outcome.extend([(5, Ok(())), (6, Err("six"))]);
+        assert_eq!(outcome.succeeded(), [1, 3, 5]);
+        assert_eq!(
+            outcome.retry_items().copied().collect::<Vec<_>>(),
+            [2, 4, 6]
+        );
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut outcome = outcome();
+        let mut other = PartialOutcome::new();
+        other.push_success(5);
+        other.push_failure(6, "six");
+        outcome.merge(other);
+        assert_eq!(
+            outcome.into_inner(),
+            (vec![1, 3, 5], vec![(2, "two"), (4, "four"), (6, "six")])
+        );
+    }
+
+    #[test]
+    fn test_into_result() {
+        let complete: PartialOutcome<u32, &str> = [(1, Ok(())), (2, Ok(()))].into_iter().collect();
+        assert_eq!(complete.into_result(), Ok(vec![1, 2]));
+        let outcome = outcome();
+        assert_eq!(outcome.clone().into_result(), Err(outcome));
+    }
+
+    #[test]
+    fn test_into_errors() {
+        assert_eq!(outcome().into_errors().into_inner(), ["two", "four"]);
+    }
+}
@@ -0,0 +1,375 @@
+//! Reporting of progress during long running operations.
+
+use std::time::{Duration, Instant};
+
+/// Receiver of progress updates.
+///
+/// Operations are divided into phases, each processing a number of items
+/// which may not be known in advance.
+pub trait Progress {
+    /// Called when a new phase starts, replacing the previous one.
+    fn start(&mut self, phase: &str, total: Option<u64>);
+
+    /// Called when the total number of items of the current phase becomes known or changes.
+    fn set_total(&mut self, total: Option<u64>);
+
+    /// Called when `items` further items of the current phase are done.
+    fn advance(&mut self, items: u64);
+
+    /// Called when the current phase is finished.
+    fn finish(&mut self);
+
+    /// Called with an estimate of the time remaining until the current phase is finished.
+    ///
+    /// Only wrappers like [`Eta`] provide estimates, the default implementation ignores them.
+    fn estimate(&mut self, _remaining: Option<Duration>) {}
+}
+
+/// Ignores all updates.
+impl Progress for () {
+    fn start(&mut self, _phase: &str, _total: Option<u64>) {}
+
+    fn set_total(&mut self, _total: Option<u64>) {}
+
+    fn advance(&mut self, _items: u64) {}
+
+    fn finish(&mut self) {}
+}
+
+impl<P: Progress + ?Sized> Progress for &mut P {
+    fn start(&mut self, phase: &str, total: Option<u64>) {
+        (**self).start(phase, total)
+    }
+
+    fn set_total(&mut self, total: Option<u64>) {
+        (**self).set_total(total)
+    }
+
+    fn advance(&mut self, items: u64) {
+        (**self).advance(items)
+    }
+
+    fn finish(&mut self) {
+        (**self).finish()
+    }
+
+    fn estimate(&mut self, remaining: Option<Duration>) {
+        (**self).estimate(remaining)
+    }
+}
+
+impl<P: Progress + ?Sized> Progress for Box<P> {
+    fn start(&mut self, phase: &str, total: Option<u64>) {
+        (**self).start(phase, total)
+    }
+
+    fn set_total(&mut self, total: Option<u64>) {
+        (**self).set_total(total)
+    }
+
+    fn advance(&mut self, items: u64) {
+        (**self).advance(items)
+    }
+
+    fn finish(&mut self) {
+        (**self).finish()
+    }
+
+    fn estimate(&mut self, remaining: Option<Duration>) {
+        (**self).estimate(remaining)
+    }
+}
+
+/// Displays the phase as message of the progress bar.
+#[cfg(feature = "indicatif")]
+impl Progress for indicatif::ProgressBar {
+    fn start(&mut self, phase: &str, total: Option<u64>) {
+        self.reset();
+        self.set_message(phase.to_owned());
+        Progress::set_total(self, total);
+    }
+
+    fn set_total(&mut self, total: Option<u64>) {
+        match total {
+            Some(total) => self.set_length(total),
+            None => self.unset_length(),
+        }
+    }
+
+    fn advance(&mut self, items: u64) {
+        self.inc(items)
+    }
+
+    fn finish(&mut self) {
+        indicatif::ProgressBar::finish(self)
+    }
+}
+
+/// [`Progress`] recording the state of the current phase.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ProgressState {
+    phase: Option<String>,
+    done: u64,
+    total: Option<u64>,
+    finished: bool,
+    remaining: Option<Duration>,
+}
+
+impl ProgressState {
+    /// Create a new state without a phase.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the name of the current phase.
+    pub fn phase(&self) -> Option<&str> {
+        self.phase.as_deref()
+    }
+
+    /// Return the number of items done in the current phase.
+    pub fn done(&self) -> u64 {
+        self.done
+    }
+
+    /// Return the total number of items of the current phase, if known.
+    pub fn total(&self) -> Option<u64> {
+        self.total
+    }
+
+    /// Check whether the current phase is finished.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Return the last estimate of the time remaining in the current phase.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.remaining
+    }
+
+    /// Return the fraction of items done in the current phase, if the total is known.
+    pub fn fraction(&self) -> Option<f64> {
+        match self.total {
+            Some(0) => Some(1.0),
+            Some(total) => Some((self.done as f64 / total as f64).min(1.0)),
+            None => None,
+        }
+    }
+}
+
+impl Progress for ProgressState {
+    fn start(&mut self, phase: &str, total: Option<u64>) {
+        self.phase = Some(phase.to_owned());
+        self.done = 0;
+        self.total = total;
+        self.finished = false;
+        self.remaining = None;
+    }
+
+    fn set_total(&mut self, total: Option<u64>) {
+        self.total = total;
+    }
+
+    fn advance(&mut self, items: u64) {
+        self.done = self.done.saturating_add(items);
+    }
+
+    fn finish(&mut self) {
+        self.finished = true;
+    }
+
+    fn estimate(&mut self, remaining: Option<Duration>) {
+        self.remaining = remaining;
+    }
+}
+
+/// [`Progress`] wrapper estimating the time remaining in each phase.
+///
+/// The estimate is derived from the average time per item observed since the
+/// phase started and passed to [`Progress::estimate`] of the wrapped receiver
+/// after every update.
+#[derive(Debug, Clone)]
+pub struct Eta<P> {
+    inner: P,
+    started: Option<Instant>,
+    done: u64,
+    total: Option<u64>,
+}
+
+impl<P> Eta<P> {
+    /// Wrap a receiver of progress updates.
+    pub fn new(inner: P) -> Self {
+        Self {
+            inner,
+            started: None,
+            done: 0,
+            total: None,
+        }
+    }
+
+    /// Return the average time per item in the current phase.
+    pub fn item_latency(&self) -> Option<Duration> {
+        scale(self.started?.elapsed(), 1, self.done)
+    }
+
+    /// Return the estimated time remaining in the current phase.
+    ///
+    /// No estimate is available before the first item is done, while the total is unknown
+    /// or if the estimate is too large to be represented.
+    pub fn remaining(&self) -> Option<Duration> {
+        let remaining = self.total?.saturating_sub(self.done);
+        scale(self.started?.elapsed(), remaining, self.done)
+    }
+
+    /// Return a reference to the wrapped receiver.
+    pub fn get_ref(&self) -> &P {
+        &self.inner
+    }
+
+    /// Return the wrapped receiver.
+    pub fn into_inner(self) -> P {
+        self.inner
+    }
+}
+
+/// Compute `duration * numerator / denominator`, returning `None` if `denominator` is zero
+/// or the result can not be represented.
+fn scale(duration: Duration, numerator: u64, denominator: u64) -> Option<Duration> {
+    if denominator == 0 {
+        return None;
+    }
+    Duration::try_from_secs_f64(duration.as_secs_f64() * (numerator as f64 / denominator as f64))
+        .ok()
+}
+
+impl<P: Progress> Progress for Eta<P> {
+    fn start(&mut self, phase: &str, total: Option<u64>) {
+        self.started = Some(Instant::now());
+        self.done = 0;
+        self.total = total;
+        self.inner.start(phase, total);
+        self.inner.estimate(None);
+    }
+
+    fn set_total(&mut self, total: Option<u64>) {
+        self.total = total;
+        self.inner.set_total(total);
+        self.inner.estimate(self.remaining());
+    }
+
+    fn advance(&mut self, items: u64) {
+        self.done = self.done.saturating_add(items);
+        self.inner.advance(items);
+        self.inner.estimate(self.remaining());
+    }
+
+    fn finish(&mut self) {
+        self.started = None;
+        self.inner.finish();
+        self.inner.estimate(Some(Duration::ZERO));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state() {
+        let mut state = ProgressState::new();
+        assert_eq!(state.phase(), None);
+        assert_eq!(state.fraction(), None);
+        state.start("scan", None);
+        state.advance(3);
+        assert_eq!(state.phase(), Some("scan"));
+        assert_eq!(state.done(), 3);
+        assert_eq!(state.fraction(), None);
+        state.set_total(Some(4));
+        assert_eq!(state.total(), Some(4));
+        assert_eq!(state.fraction(), Some(0.75));
+        state.advance(2);
+        assert_eq!(state.fraction(), Some(1.0));
+        assert!(!state.is_finished());
+        state.finish();
+        assert!(state.is_finished());
+    }
+
+    #[test]
+    fn test_state_restart() {
+        let mut state = ProgressState::new();
+        state.start("scan", Some(2));
+        state.advance(2);
+        state.estimate(Some(Duration::from_secs(1)));
+        state.finish();
+        state.start("delete", Some(0));
+        assert_eq!(state.phase(), Some("delete"));
+        assert_eq!(state.done(), 0);
+        assert_eq!(state.remaining(), None);
+        assert_eq!(state.fraction(), Some(1.0));
+        assert!(!state.is_finished());
+    }
+
+    #[test]
+    fn test_scale() {
+        let second = Duration::from_secs(1);
+        assert_eq!(scale(second, 3, 2), Some(Duration::from_millis(1500)));
+        assert_eq!(scale(second, 0, 2), Some(Duration::ZERO));
+        assert_eq!(scale(second, 1, 0), None);
+        assert_eq!(scale(Duration::from_secs(2), u64::MAX, 1), None);
+    }
+
+    #[test]
+    fn test_eta() {
+        let mut eta = Eta::new(ProgressState::new());
+        assert_eq!(eta.remaining(), None);
+        eta.start("scan", None);
+        eta.advance(1);
+        assert_eq!(eta.remaining(), None);
+        assert_eq!(eta.get_ref().remaining(), None);
+        assert!(eta.item_latency().is_some());
+        eta.set_total(Some(1));
+        assert_eq!(eta.remaining(), Some(Duration::ZERO));
+        assert_eq!(eta.get_ref().remaining(), Some(Duration::ZERO));
+        eta.start("delete", Some(u64::MAX));
+        assert_eq!(eta.item_latency(), None);
+        assert_eq!(eta.get_ref().remaining(), None);
+        eta.advance(1);
+        assert_eq!(eta.get_ref().done(), 1);
+        eta.finish();
+        assert_eq!(eta.remaining(), None);
+        let state = eta.into_inner();
+        assert!(state.is_finished());
+        assert_eq!(state.remaining(), Some(Duration::ZERO));
+    }
+
+    #[cfg(feature = "indicatif")]
+    #[test]
+    fn test_indicatif() {
+        let mut bar = indicatif::ProgressBar::hidden();
+        Progress::start(&mut bar, "scan", Some(4));
+        Progress::advance(&mut bar, 3);
+        assert_eq!(bar.message(), "scan");
+        assert_eq!(bar.length(), Some(4));
+        assert_eq!(bar.position(), 3);
+        Progress::finish(&mut bar);
+        assert!(bar.is_finished());
+        Progress::start(&mut bar, "delete", None);
+        assert_eq!(bar.message(), "delete");
+        assert_eq!(bar.length(), None);
+        assert_eq!(bar.position(), 0);
+        assert!(!bar.is_finished());
+    }
+
+    #[test]
+    fn test_forwarding() {
+        let mut state = ProgressState::new();
+        let mut progress: Box<dyn Progress + '_> = Box::new(&mut state);
+        progress.start("scan", Some(2));
+        progress.advance(1);
+        progress.estimate(Some(Duration::from_secs(1)));
+        progress.finish();
+        drop(progress);
+        assert_eq!(state.done(), 1);
+        assert_eq!(state.remaining(), Some(Duration::from_secs(1)));
+        assert!(state.is_finished());
+    }
+}